@@ -0,0 +1,91 @@
+use serde_json::{json, Value};
+
+/// Mirrors Gemini/Vertex AI's `generationConfig` object. Defaults match what `call_llm_api`
+/// used to hardcode, so existing callers see no behavior change unless they opt in.
+#[derive(Clone, Debug)]
+pub struct GenerationConfig {
+    pub temperature: f32,
+    pub top_k: u32,
+    pub top_p: f32,
+    pub max_output_tokens: Option<u32>,
+    pub candidate_count: Option<u32>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            temperature: 0.1,
+            top_k: 1,
+            top_p: 1.0,
+            max_output_tokens: None,
+            candidate_count: None,
+        }
+    }
+}
+
+impl GenerationConfig {
+    pub(crate) fn to_json(&self) -> Value {
+        let mut config = json!({
+            "temperature": self.temperature,
+            "topK": self.top_k,
+            "topP": self.top_p,
+        });
+        if let Some(max_output_tokens) = self.max_output_tokens {
+            config["maxOutputTokens"] = json!(max_output_tokens);
+        }
+        if let Some(candidate_count) = self.candidate_count {
+            config["candidateCount"] = json!(candidate_count);
+        }
+        config
+    }
+}
+
+/// How aggressively Gemini/Vertex AI should block content across the harassment, hate-speech,
+/// sexually-explicit, and dangerous-content categories.
+#[derive(Clone, Copy, Debug)]
+pub enum BlockThreshold {
+    BlockNone,
+    BlockOnlyHigh,
+    BlockMediumAndAbove,
+}
+
+impl BlockThreshold {
+    fn as_str(self) -> &'static str {
+        match self {
+            BlockThreshold::BlockNone => "BLOCK_NONE",
+            BlockThreshold::BlockOnlyHigh => "BLOCK_ONLY_HIGH",
+            BlockThreshold::BlockMediumAndAbove => "BLOCK_MEDIUM_AND_ABOVE",
+        }
+    }
+
+    pub(crate) fn to_safety_settings(self) -> Value {
+        const CATEGORIES: [&str; 4] = [
+            "HARM_CATEGORY_HARASSMENT",
+            "HARM_CATEGORY_HATE_SPEECH",
+            "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+            "HARM_CATEGORY_DANGEROUS_CONTENT",
+        ];
+
+        let threshold = self.as_str();
+        json!(CATEGORIES
+            .iter()
+            .map(|category| json!({ "category": category, "threshold": threshold }))
+            .collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safety_settings_cover_all_categories_with_the_chosen_threshold() {
+        let settings = BlockThreshold::BlockOnlyHigh.to_safety_settings();
+        let settings = settings.as_array().unwrap();
+
+        assert_eq!(settings.len(), 4);
+        for setting in settings {
+            assert_eq!(setting["threshold"], "BLOCK_ONLY_HIGH");
+        }
+    }
+}
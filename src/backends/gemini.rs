@@ -0,0 +1,104 @@
+use std::any::Any;
+use std::error::Error;
+
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+
+use crate::rest_gemini_client::INIT_MESSAGE;
+
+use super::gemini_common;
+use super::{BlockThreshold, GenerationConfig, LlmBackend};
+
+/// Talks to Google's Gemini API (`generativelanguage.googleapis.com`) using an API key.
+pub struct GeminiBackend {
+    pub(crate) api_key: String,
+    pub(crate) model: String,
+    pub(crate) generation_config: GenerationConfig,
+    pub(crate) block_threshold: Option<BlockThreshold>,
+}
+
+impl GeminiBackend {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            model: "gemini-1.5-flash".to_string(),
+            generation_config: GenerationConfig::default(),
+            block_threshold: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_generation_config(mut self, generation_config: GenerationConfig) -> Self {
+        self.generation_config = generation_config;
+        self
+    }
+
+    pub fn with_block_threshold(mut self, block_threshold: BlockThreshold) -> Self {
+        self.block_threshold = Some(block_threshold);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmBackend for GeminiBackend {
+    fn build_request(&self, client: &Client, prompt: &str, response_schema: Option<&Value>) -> RequestBuilder {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            self.model, self.api_key
+        );
+
+        let request_body = gemini_common::build_request_body(
+            vec![json!({ "text": format!("{}{}", INIT_MESSAGE, prompt) })],
+            &self.generation_config,
+            self.block_threshold,
+            response_schema,
+        );
+
+        client
+            .post(&url)
+            .header("Host", "generativelanguage.googleapis.com")
+            .json(&request_body)
+    }
+
+    fn parse_response(&self, json: &Value, status: StatusCode) -> Result<String, Box<dyn Error + Send + Sync>> {
+        gemini_common::parse_generate_content_response(json, status)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A single piece of a multimodal prompt: plain text, or an inline image/audio/etc. blob for
+/// vision-capable models.
+pub enum Part {
+    Text(String),
+    InlineData { mime_type: String, base64_data: String },
+}
+
+impl Part {
+    /// Base64-encodes raw bytes (e.g. a screenshot) into an inline data part.
+    pub fn inline_data(mime_type: impl Into<String>, bytes: &[u8]) -> Self {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        Part::InlineData {
+            mime_type: mime_type.into(),
+            base64_data: STANDARD.encode(bytes),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        match self {
+            Part::Text(text) => json!({ "text": text }),
+            Part::InlineData { mime_type, base64_data } => json!({
+                "inline_data": {
+                    "mime_type": mime_type,
+                    "data": base64_data
+                }
+            }),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Request-building and response-parsing shared by `GeminiBackend` and `VertexAiBackend`: both
+//! talk to the same `generateContent` request/response shape, just over different transports.
+
+use std::error::Error;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::rest_gemini_client::ApiError;
+
+use super::{BlockThreshold, BlockedError, GenerationConfig};
+
+/// Builds `generationConfig`, layering in `responseMimeType`/`responseSchema` when a response
+/// schema is configured (Gemini's native structured-output support).
+pub(crate) fn build_generation_config(generation_config: &GenerationConfig, response_schema: Option<&Value>) -> Value {
+    let mut config = generation_config.to_json();
+    if let Some(schema) = response_schema {
+        config["responseMimeType"] = json!("application/json");
+        config["responseSchema"] = schema.clone();
+    }
+    config
+}
+
+/// Builds the full `contents`/`generationConfig`/`safetySettings` request body from already
+/// built `parts`.
+pub(crate) fn build_request_body(
+    parts: Vec<Value>,
+    generation_config: &GenerationConfig,
+    block_threshold: Option<BlockThreshold>,
+    response_schema: Option<&Value>,
+) -> Value {
+    let mut body = json!({
+        "contents": [{
+            "parts": parts,
+            "role": "user"
+        }],
+        "generationConfig": build_generation_config(generation_config, response_schema)
+    });
+
+    if let Some(block_threshold) = block_threshold {
+        body["safetySettings"] = block_threshold.to_safety_settings();
+    }
+
+    body
+}
+
+/// Parses a `generateContent` response: a `promptFeedback.blockReason` or empty `candidates`
+/// means the prompt/response was blocked; otherwise extracts `candidates[0].content.parts[0].text`.
+pub(crate) fn parse_generate_content_response(json: &Value, status: StatusCode) -> Result<String, Box<dyn Error + Send + Sync>> {
+    if let Some(block_reason) = json["promptFeedback"]["blockReason"].as_str() {
+        return Err(Box::new(BlockedError {
+            reason: block_reason.to_string(),
+        }));
+    }
+
+    if json["candidates"].as_array().map_or(true, |c| c.is_empty()) {
+        return Err(Box::new(BlockedError {
+            reason: "no candidates returned".to_string(),
+        }));
+    }
+
+    json["candidates"][0]["content"]["parts"][0]["text"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| {
+            Box::new(ApiError {
+                status,
+                message: "Failed to extract text from response".to_string(),
+            }) as Box<dyn Error + Send + Sync>
+        })
+}
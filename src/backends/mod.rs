@@ -0,0 +1,56 @@
+use std::any::Any;
+use std::error::Error;
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::Value;
+
+mod config;
+mod gemini;
+pub(crate) mod gemini_common;
+mod openai;
+mod vertex;
+
+pub use config::{BlockThreshold, GenerationConfig};
+pub use gemini::{GeminiBackend, Part};
+pub use openai::OpenAiCompatibleBackend;
+pub use vertex::VertexAiBackend;
+
+/// Returned by `parse_response` when the provider blocked the prompt or response instead of
+/// returning a `text` part, e.g. a `promptFeedback.blockReason` or an empty `candidates` array.
+#[derive(Debug)]
+pub struct BlockedError {
+    pub reason: String,
+}
+
+impl std::fmt::Display for BlockedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Prompt blocked by safety settings: {}", self.reason)
+    }
+}
+
+impl Error for BlockedError {}
+
+/// A pluggable LLM provider.
+///
+/// Implementors own everything provider-specific: the request URL and body shape, how
+/// credentials are attached, and how to pull the generated text back out of the response.
+/// `AiClient` drives the HTTP call and the shared JSON-command validation on top.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// `response_schema`, when set, asks the provider to constrain generation to that JSON
+    /// schema (e.g. Gemini/Vertex's `generationConfig.responseSchema`). Backends that don't
+    /// support structured output are free to ignore it.
+    fn build_request(&self, client: &Client, prompt: &str, response_schema: Option<&Value>) -> RequestBuilder;
+    fn parse_response(&self, json: &Value, status: StatusCode) -> Result<String, Box<dyn Error + Send + Sync>>;
+
+    /// Allows downcasting to a concrete backend for provider-specific features (e.g. the
+    /// Gemini-only streaming API) that don't make sense on the trait itself.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Runs before every call so a backend can refresh time-limited credentials (e.g. Vertex
+    /// AI's OAuth access token) before `build_request` reads them. Most backends don't need this.
+    async fn prepare(&self, _client: &Client) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::any::Any;
+use std::error::Error;
+
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+
+use crate::rest_gemini_client::{ApiError, INIT_MESSAGE};
+
+use super::{GenerationConfig, LlmBackend};
+
+/// Talks to any endpoint implementing the OpenAI `/v1/chat/completions` shape
+/// (LocalAI, vLLM's OpenAI-compatible server, etc.).
+pub struct OpenAiCompatibleBackend {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    generation_config: GenerationConfig,
+}
+
+impl OpenAiCompatibleBackend {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: None,
+            model: model.into(),
+            generation_config: GenerationConfig::default(),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_generation_config(mut self, generation_config: GenerationConfig) -> Self {
+        self.generation_config = generation_config;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmBackend for OpenAiCompatibleBackend {
+    fn build_request(&self, client: &Client, prompt: &str, _response_schema: Option<&Value>) -> RequestBuilder {
+        let url = format!("{}/v1/chat/completions", self.base_url.trim_end_matches('/'));
+
+        // Chat Completions' tunable fields map loosely onto GenerationConfig: `topK` has no
+        // equivalent here and is dropped, `candidate_count` becomes `n`.
+        let mut request_body = json!({
+            "model": self.model,
+            "messages": [{
+                "role": "user",
+                "content": format!("{}{}", INIT_MESSAGE, prompt)
+            }],
+            "temperature": self.generation_config.temperature,
+            "top_p": self.generation_config.top_p,
+        });
+        if let Some(max_tokens) = self.generation_config.max_output_tokens {
+            request_body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(n) = self.generation_config.candidate_count {
+            request_body["n"] = json!(n);
+        }
+
+        let builder = client.post(&url).json(&request_body);
+        match &self.api_key {
+            Some(api_key) => builder.bearer_auth(api_key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, json: &Value, status: StatusCode) -> Result<String, Box<dyn Error + Send + Sync>> {
+        json["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| {
+                Box::new(ApiError {
+                    status,
+                    message: "Failed to extract text from response".to_string(),
+                }) as Box<dyn Error + Send + Sync>
+            })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
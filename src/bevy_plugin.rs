@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use tokio::sync::oneshot;
+
+use crate::rest_gemini_client::AiClient;
+
+/// Fired to ask the AI for a response to `prompt`. The result comes back later as an
+/// `AiResponse` or `AiError` event once the underlying async call resolves.
+#[derive(Event, Clone)]
+pub struct PromptRequest {
+    pub prompt: String,
+}
+
+#[derive(Event)]
+pub struct AiResponse {
+    pub text: String,
+}
+
+#[derive(Event)]
+pub struct AiError {
+    pub message: String,
+}
+
+/// Wraps a `tokio::runtime::Handle` as a Bevy `Resource` (the orphan rule blocks implementing
+/// it directly on a foreign type).
+///
+/// `reqwest`'s TLS/reactor code needs a Tokio runtime underneath it; Bevy's own
+/// `AsyncComputeTaskPool` is an `async-executor`, not Tokio, so HTTP calls are spawned on this
+/// handle instead of on the compute pool.
+#[derive(Resource, Clone)]
+struct TokioHandle(tokio::runtime::Handle);
+
+#[derive(Component)]
+struct PromptTask(oneshot::Receiver<Result<String, String>>);
+
+/// Bevy integration for `AiClient`: write a `PromptRequest` event to fire a prompt, read the
+/// parsed command back as an `AiResponse`/`AiError` event, without blocking the schedule or
+/// managing tokio manually.
+pub struct AiPlugin {
+    client: AiClient,
+    handle: tokio::runtime::Handle,
+}
+
+impl AiPlugin {
+    /// `handle` is the Tokio runtime the host application is already running (e.g. from
+    /// `#[tokio::main]` or a manually built `Runtime`); the plugin spawns each HTTP call onto
+    /// it rather than Bevy's compute task pool.
+    pub fn new(client: AiClient, handle: tokio::runtime::Handle) -> Self {
+        Self { client, handle }
+    }
+}
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.client.clone())
+            .insert_resource(TokioHandle(self.handle.clone()))
+            .add_event::<PromptRequest>()
+            .add_event::<AiResponse>()
+            .add_event::<AiError>()
+            .add_systems(Update, (spawn_prompt_tasks, poll_prompt_tasks));
+    }
+}
+
+fn spawn_prompt_tasks(
+    mut commands: Commands,
+    mut requests: EventReader<PromptRequest>,
+    client: Res<AiClient>,
+    handle: Res<TokioHandle>,
+) {
+    for request in requests.read() {
+        let client = client.clone();
+        let prompt = request.prompt.clone();
+        let (tx, rx) = oneshot::channel();
+
+        handle.0.spawn(async move {
+            let result = client.call_llm_api(&prompt).await.map_err(|e| e.to_string());
+            let _ = tx.send(result);
+        });
+
+        commands.spawn(PromptTask(rx));
+    }
+}
+
+fn poll_prompt_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut PromptTask)>,
+    mut responses: EventWriter<AiResponse>,
+    mut errors: EventWriter<AiError>,
+) {
+    for (entity, mut task) in &mut tasks {
+        let result = match task.0.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => continue,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                Err("AI task dropped without sending a result".to_string())
+            }
+        };
+
+        match result {
+            Ok(text) => {
+                responses.send(AiResponse { text });
+            }
+            Err(message) => {
+                errors.send(AiError { message });
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
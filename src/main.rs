@@ -1,4 +1,7 @@
+mod backends;
+mod bevy_plugin;
 mod rest_gemini_client;
+mod schema;
 
 use rest_gemini_client::AiClient;
 
@@ -1,9 +1,14 @@
 use std::{error::Error, time::Duration, net::ToSocketAddrs};
 use std::fmt;
+use futures_util::StreamExt;
 use reqwest::{Client, ClientBuilder};
 use serde_json::{json, Value};
+use tokio::sync::mpsc;
 use bevy::prelude::*;
 
+use crate::backends::{gemini_common, GeminiBackend, LlmBackend, Part};
+use crate::schema;
+
 pub const INIT_MESSAGE: &str = "\
 enter a prompt to generate a response from the AI model
 {
@@ -15,29 +20,51 @@ enter a prompt to generate a response from the AI model
 }
 Always respond with valid JSON in the exact format shown above. Here is the prompt:";
 
+/// Strips a leading/trailing markdown code fence (```` ```json ```` or plain ```` ``` ````),
+/// which models reach for unprompted when `responseMimeType` isn't set to force raw JSON.
+fn strip_json_fence(text: &str) -> &str {
+    let text = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .unwrap_or(text);
+    text.strip_suffix("```").unwrap_or(text).trim()
+}
+
+/// Fallback schema used when no `response_schema` is configured: just enough structure to
+/// distinguish a real command response from garbage.
+fn default_response_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["command"],
+        "properties": {
+            "command": { "type": "string" }
+        }
+    })
+}
+
 #[derive(Resource, Clone)]
 pub struct AiClient {
-    api_key: String,
+    backend: std::sync::Arc<dyn LlmBackend>,
     client: Client,
+    response_schema: Option<Value>,
 }
 
 impl Default for AiClient {
     fn default() -> Self {
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(30))
-            .tcp_keepalive(Some(Duration::from_secs(60)))
-            .pool_max_idle_per_host(0)
-            .build()
-            .expect("Failed to create client");
-        Self {
-            api_key: String::new(),
-            client,
-        }
+        Self::with_backend(GeminiBackend::new(String::new()))
     }
 }
 
 impl AiClient {
+    /// Convenience constructor for the common case: talk to Gemini with an API key.
     pub fn new(api_key: String) -> Self {
+        Self::with_backend(GeminiBackend::new(api_key))
+    }
+
+    /// Builds a client around any `LlmBackend`, e.g. `OpenAiCompatibleBackend` or
+    /// `VertexAiBackend`, so the rest of `AiClient` (connectivity test, validation) works the
+    /// same regardless of provider.
+    pub fn with_backend(backend: impl LlmBackend + 'static) -> Self {
         let client = ClientBuilder::new()
             .timeout(Duration::from_secs(30))
             .tcp_keepalive(Some(Duration::from_secs(60)))
@@ -45,11 +72,19 @@ impl AiClient {
             .build()
             .expect("Failed to create client");
         Self {
-            api_key,
+            backend: std::sync::Arc::new(backend),
             client,
+            response_schema: None,
         }
     }
 
+    /// Constrains responses to the given JSON schema (Gemini's native structured-output
+    /// support) instead of relying on hand-validated ad hoc command matching.
+    pub fn with_response_schema(mut self, response_schema: Value) -> Self {
+        self.response_schema = Some(response_schema);
+        self
+    }
+
     pub async fn test_basic_connectivity(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
         let host = "generativelanguage.googleapis.com:443";
         println!("Testing basic DNS resolution for: {}", host);
@@ -77,35 +112,13 @@ impl AiClient {
         println!("Connectivity test passed, proceeding with API call");
         println!("Calling LLM API with prompt: {}", prompt);
 
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-1.5-flash:generateContent?key={}",
-            self.api_key
-        );
-
-        let request_body = json!({
-            "contents": [{
-                "parts": [{
-                    "text": format!("{}{}", INIT_MESSAGE, prompt)
-                }],
-                "role": "user"
-            }],
-            "generationConfig": {
-                "temperature": 0.1,
-                "topK": 1,
-                "topP": 1
-            }
-        });
+        self.backend.prepare(&self.client).await?;
+        let request = self.backend.build_request(&self.client, prompt, self.response_schema.as_ref());
 
-        match self.client
-            .post(&url)
-            .header("Host", "generativelanguage.googleapis.com")
-            .json(&request_body)
-            .send()
-            .await
-        {
+        match request.send().await {
             Ok(response) => {
                 println!("Received response with status: {}", response.status());
-                handle_response(response).await
+                handle_response(response, self.backend.as_ref(), self.response_schema.as_ref()).await
             },
             Err(e) => {
                 println!("Error sending request: {:?}", e);
@@ -119,78 +132,190 @@ impl AiClient {
             }
         }
     }
+
+    /// Streams a Gemini response incrementally instead of waiting for the full completion.
+    ///
+    /// Returns a channel that yields each text delta as it arrives; the channel closes once
+    /// the stream ends or the underlying request fails. Only supported on the Gemini backend,
+    /// since the SSE framing below is specific to `streamGenerateContent`.
+    pub async fn call_llm_api_stream(
+        &self,
+        prompt: &str,
+    ) -> Result<mpsc::Receiver<Result<String, Box<dyn Error + Send + Sync>>>, Box<dyn Error + Send + Sync>> {
+        let gemini = self.backend.as_any().downcast_ref::<GeminiBackend>().ok_or_else(|| {
+            Box::new(ApiError {
+                status: reqwest::StatusCode::BAD_REQUEST,
+                message: "Streaming is only supported by the Gemini backend".to_string(),
+            }) as Box<dyn Error + Send + Sync>
+        })?;
+
+        println!("Calling streaming LLM API with prompt: {}", prompt);
+
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse&key={}",
+            gemini.model, gemini.api_key
+        );
+
+        let request_body = gemini_common::build_request_body(
+            vec![json!({ "text": format!("{}{}", INIT_MESSAGE, prompt) })],
+            &gemini.generation_config,
+            gemini.block_threshold,
+            self.response_schema.as_ref(),
+        );
+
+        let response = self.client
+            .post(&url)
+            .header("Host", "generativelanguage.googleapis.com")
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await.unwrap_or_else(|_| "Error reading response".to_string());
+            return Err(Box::new(ApiError {
+                status,
+                message: error_body,
+            }));
+        }
+
+        let (tx, rx) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            let mut byte_stream = response.bytes_stream();
+            // Raw bytes from a read that ended mid-event, kept undecoded: SSE events are
+            // separated by a blank line and a single `data:` payload can span multiple TCP
+            // reads, so decoding eagerly (e.g. via `from_utf8_lossy` per chunk) would corrupt
+            // any multi-byte codepoint that happens to straddle a read boundary. `\n\n` is pure
+            // ASCII and can't appear inside a UTF-8 continuation sequence, so once it shows up
+            // in `buffer` everything before it is a complete event with no codepoint cut in half
+            // (bytes the server sent as invalid UTF-8 still fail to decode and are reported).
+            let mut buffer: Vec<u8> = Vec::new();
+
+            while let Some(chunk) = byte_stream.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        let _ = tx.send(Err(Box::new(e) as Box<dyn Error + Send + Sync>)).await;
+                        return;
+                    }
+                };
+                buffer.extend_from_slice(&bytes);
+
+                for event in drain_complete_sse_events(&mut buffer) {
+                    let event = match event {
+                        Ok(s) => s,
+                        Err(e) => {
+                            let _ = tx.send(Err(Box::new(e) as Box<dyn Error + Send + Sync>)).await;
+                            continue;
+                        }
+                    };
+
+                    for line in event.lines() {
+                        let Some(data) = line.strip_prefix("data: ") else {
+                            continue;
+                        };
+
+                        let parsed: Value = match serde_json::from_str(data) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                let _ = tx.send(Err(Box::new(e) as Box<dyn Error + Send + Sync>)).await;
+                                continue;
+                            }
+                        };
+
+                        if let Some(text) = parsed["candidates"][0]["content"]["parts"][0]["text"].as_str() {
+                            if tx.send(Ok(text.to_string())).await.is_err() {
+                                // Receiver dropped; stop driving the stream.
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// Sends a multimodal prompt (text mixed with inline images/audio/etc.) to a vision-capable
+    /// Gemini model, e.g. "describe this screenshot and return a command". `model` overrides the
+    /// backend's configured model name since vision models are typically distinct from the
+    /// text-only default.
+    pub async fn call_llm_api_multimodal(
+        &self,
+        model: &str,
+        parts: Vec<Part>,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let gemini = self.backend.as_any().downcast_ref::<GeminiBackend>().ok_or_else(|| {
+            Box::new(ApiError {
+                status: reqwest::StatusCode::BAD_REQUEST,
+                message: "Multimodal prompts are only supported by the Gemini backend".to_string(),
+            }) as Box<dyn Error + Send + Sync>
+        })?;
+
+        println!("Calling multimodal LLM API with model: {}", model);
+
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            model, gemini.api_key
+        );
+
+        // `handle_response` below validates the reply as a JSON command just like `call_llm_api`
+        // does, so the model needs the same command-format instructions up front; otherwise a
+        // prompt like "describe this screenshot" just gets prose back and fails validation.
+        let mut content_parts = vec![json!({ "text": INIT_MESSAGE })];
+        content_parts.extend(parts.iter().map(Part::to_json));
+
+        let request_body = gemini_common::build_request_body(
+            content_parts,
+            &gemini.generation_config,
+            gemini.block_threshold,
+            self.response_schema.as_ref(),
+        );
+
+        let response = self.client
+            .post(&url)
+            .header("Host", "generativelanguage.googleapis.com")
+            .json(&request_body)
+            .send()
+            .await?;
+
+        handle_response(response, self.backend.as_ref(), self.response_schema.as_ref()).await
+    }
 }
 
-async fn handle_response(res: reqwest::Response) -> Result<String, Box<dyn Error + Send + Sync>> {
+async fn handle_response(
+    res: reqwest::Response,
+    backend: &dyn LlmBackend,
+    response_schema: Option<&Value>,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
     let status = res.status();
     if status.is_success() {
         let response_json: serde_json::Value = res.json().await?;
         println!("Response: {}", response_json);
-        
-        // Extract the text from Gemini's response format
-        let raw_text = response_json["candidates"][0]["content"]["parts"][0]["text"]
-            .as_str()
-            .ok_or_else(|| ApiError {
-                status,
-                message: "Failed to extract text from response".to_string(),
-            })?;
 
-        // Clean response
-        let trimmed = raw_text.trim();
-        let without_markers = trimmed.trim_start_matches("```json").trim_end_matches("```");
-        let without_newlines = without_markers.replace("\n", "");
-        let text = without_newlines.trim();
+        // Extract the text using the provider-specific response shape. Structured output
+        // (`response_schema` set) means the model already emits clean JSON, but without it the
+        // model is only following INIT_MESSAGE's prose instructions and commonly wraps its
+        // answer in a ```json fence anyway, so strip one if present before parsing.
+        let raw_text = backend.parse_response(&response_json, status)?;
+        let text = strip_json_fence(raw_text.trim());
 
-        // Parse the text as JSON to validate it's a proper JSON response
         let json_value: Value = serde_json::from_str(text)
             .map_err(|e| ApiError {
                 status,
                 message: format!("Invalid JSON in response: {}", e),
             })?;
 
-        // Verify the JSON has the expected structure
-        if !json_value.is_object() || !json_value["command"].is_string() {
+        let schema = response_schema.cloned().unwrap_or_else(default_response_schema);
+        if let Err(reason) = schema::validate(&json_value, &schema) {
             return Err(Box::new(ApiError {
                 status,
-                message: "Response JSON missing required fields".to_string(),
+                message: format!("Response JSON does not match schema: {}", reason),
             }));
         }
 
-        // Check parameters based on command type
-        let command = json_value["command"].as_str().unwrap();
-        match command {
-            "greeting" => {
-                if !json_value["parameters"].is_object() {
-                    return Err(Box::new(ApiError {
-                        status,
-                        message: "Single parameters must be an object".to_string(),
-                    }));
-                }
-            },
-            "1" => {
-                if !json_value["parameters"].is_object() {
-                    return Err(Box::new(ApiError {
-                        status,
-                        message: "Single parameters must be an object".to_string(),
-                    }));
-                }
-            },
-            "2" => {
-                if !json_value["parameters"].is_array() {
-                    return Err(Box::new(ApiError {
-                        status,
-                        message: "Multiple parameters must be an array".to_string(),
-                    }));
-                }
-            },
-            _ => {
-                return Err(Box::new(ApiError {
-                    status,
-                    message: format!("Unknown command: {}", command),
-                }));
-            }
-        }
-
         Ok(text.to_string())
     } else {
         let error_body = res.text().await.unwrap_or_else(|_| "Error reading response".to_string());
@@ -218,3 +343,76 @@ impl fmt::Display for ApiError {
 }
 
 impl Error for ApiError {}
+
+/// Drains every complete `\n\n`-delimited SSE event out of `buffer`, leaving any trailing
+/// partial event (including a codepoint split across a TCP read boundary) for the next call.
+fn drain_complete_sse_events(buffer: &mut Vec<u8>) -> Vec<Result<String, std::string::FromUtf8Error>> {
+    let mut events = Vec::new();
+    while let Some(pos) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let event_bytes: Vec<u8> = buffer.drain(..pos + 2).collect();
+        events.push(String::from_utf8(event_bytes));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_no_events_from_a_partial_buffer() {
+        let mut buffer = b"data: {\"candidates\":[]}".to_vec();
+        let events = drain_complete_sse_events(&mut buffer);
+
+        assert!(events.is_empty());
+        assert_eq!(buffer, b"data: {\"candidates\":[]}".to_vec());
+    }
+
+    #[test]
+    fn extracts_multiple_complete_events_in_one_pass() {
+        let mut buffer = b"data: one\n\ndata: two\n\n".to_vec();
+        let events: Vec<String> = drain_complete_sse_events(&mut buffer).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(events, vec!["data: one\n\n", "data: two\n\n"]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn leaves_a_trailing_partial_event_undrained() {
+        let mut buffer = b"data: one\n\ndata: two".to_vec();
+        let events: Vec<String> = drain_complete_sse_events(&mut buffer).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(events, vec!["data: one\n\n"]);
+        assert_eq!(buffer, b"data: two".to_vec());
+    }
+
+    #[test]
+    fn does_not_split_a_multibyte_codepoint_straddling_two_reads() {
+        // "café" (UTF-8: 'caf' + 0xC3 0xA9) split mid-codepoint across two simulated reads.
+        let full_event = "data: café\n\n".as_bytes();
+        let (first, second) = full_event.split_at(full_event.len() - 3);
+
+        let mut buffer = first.to_vec();
+        assert!(drain_complete_sse_events(&mut buffer).is_empty());
+
+        buffer.extend_from_slice(second);
+        let events: Vec<String> = drain_complete_sse_events(&mut buffer).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(events, vec!["data: café\n\n"]);
+    }
+
+    #[test]
+    fn strip_json_fence_removes_a_json_tagged_fence() {
+        assert_eq!(strip_json_fence("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn strip_json_fence_removes_a_plain_fence() {
+        assert_eq!(strip_json_fence("```\n{\"a\": 1}\n```"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn strip_json_fence_leaves_unfenced_json_alone() {
+        assert_eq!(strip_json_fence("{\"a\": 1}"), "{\"a\": 1}");
+    }
+}
@@ -0,0 +1,141 @@
+use serde_json::Value;
+
+/// Minimal JSON Schema validator covering what a Gemini `responseSchema` needs checked on the
+/// client side: required fields and per-property types. Not a general-purpose validator.
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    if schema["type"].as_str() == Some("object") && !value.is_object() {
+        return Err("expected a JSON object".to_string());
+    }
+
+    if let Some(required) = schema["required"].as_array() {
+        for field in required {
+            let Some(field_name) = field.as_str() else {
+                continue;
+            };
+            if value.get(field_name).is_none() {
+                return Err(format!("missing required field `{}`", field_name));
+            }
+        }
+    }
+
+    if let Some(properties) = schema["properties"].as_object() {
+        for (field_name, field_schema) in properties {
+            let Some(field_value) = value.get(field_name) else {
+                continue;
+            };
+            let Some(expected_type) = field_schema["type"].as_str() else {
+                continue;
+            };
+            if !type_matches(field_value, expected_type) {
+                return Err(format!(
+                    "field `{}` must be of type `{}`, got `{}`",
+                    field_name,
+                    expected_type,
+                    type_name(field_value)
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn command_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["command"],
+            "properties": {
+                "command": { "type": "string" },
+                "parameters": { "type": "object" }
+            }
+        })
+    }
+
+    #[test]
+    fn accepts_a_value_matching_the_schema() {
+        let value = json!({ "command": "move", "parameters": { "x": 1 } });
+        assert_eq!(validate(&value, &command_schema()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_non_object_when_schema_requires_one() {
+        let value = json!("not an object");
+        assert!(validate(&value, &command_schema()).is_err());
+    }
+
+    #[test]
+    fn rejects_a_missing_required_field() {
+        let value = json!({ "parameters": {} });
+        let err = validate(&value, &command_schema()).unwrap_err();
+        assert!(err.contains("command"), "error should name the missing field: {}", err);
+    }
+
+    #[test]
+    fn rejects_a_property_with_the_wrong_type() {
+        let value = json!({ "command": 42 });
+        let err = validate(&value, &command_schema()).unwrap_err();
+        assert!(err.contains("command"));
+        assert!(err.contains("string"));
+    }
+
+    #[test]
+    fn ignores_properties_the_schema_does_not_mention() {
+        let value = json!({ "command": "move", "extra": true });
+        assert_eq!(validate(&value, &command_schema()), Ok(()));
+    }
+
+    #[test]
+    fn type_matches_table() {
+        let cases = [
+            (json!("x"), "string", true),
+            (json!("x"), "number", false),
+            (json!(1), "number", true),
+            (json!(1), "integer", true),
+            (json!(1.5), "integer", false),
+            (json!(true), "boolean", true),
+            (json!(null), "null", true),
+            (json!([1, 2]), "array", true),
+            (json!({}), "object", true),
+        ];
+        for (value, expected_type, expected) in cases {
+            assert_eq!(
+                type_matches(&value, expected_type),
+                expected,
+                "{:?} as {} should be {}",
+                value,
+                expected_type,
+                expected
+            );
+        }
+    }
+}